@@ -3,4 +3,3 @@ mod error;
 mod macros;
 
 pub use error::{Error, Result};
-pub use macros::*;
@@ -1,138 +1,463 @@
-use crate::disk::DATA_DIR;
-use crate::PAGE_SIZE_BYTES;
-use bytes::{Bytes, BytesMut};
-use rustdb_error::{errdata, Error, Result};
-use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use crate::disk::temp_storage::TempDir;
+use crate::disk::{DATA_DIR, PAGE_SIZES_BYTES};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use rustdb_error::{errdata, errinput, Error, Result};
+use std::ops::Range;
 use std::path::Path;
+use std::sync::RwLock;
 
 pub(crate) type PageId = u64;
 
-const EMPTY_BUFFER: &'static [u8] = &[0; PAGE_SIZE_BYTES];
+/// The page holding the database header. It is never handed out by `allocate_page()`.
+const HEADER_PAGE_ID: PageId = 0;
+/// Identifies a file as a Rustdb database. Stored at the start of the header page.
+const MAGIC: &[u8; 8] = b"RUSTDB\0\0";
+/// The version of the on-disk format, bumped whenever the page or header layout changes.
+const FORMAT_VERSION: u32 = 1;
+/// The header consists of the magic bytes followed by the format version and the page size, both
+/// as big-endian `u32`s.
+const HEADER_SIZE_BYTES: usize = MAGIC.len() + 2 * size_of::<u32>();
 
-/// Handles read and write accesses to pages stored on disk. File I/O operations are synchronous.
-/// Asynchronous row operations, on the other hand, should occur on the pages buffered in memory,
-/// with the disk manager being protected behind a [tokio::sync::RwLock] synchronization primitive.
+/// Zeroes used to initialize pages, sized for the largest supported page size.
+const EMPTY_BUFFER: &[u8] = &[0; PAGE_SIZES_BYTES[PAGE_SIZES_BYTES.len() - 1]];
+
+/// The number of latches that page accesses are sharded across.
+const PAGE_LATCH_SHARDS: usize = 64;
+
+/// Handles read and write accesses to pages stored on disk. File I/O operations are synchronous,
+/// and use positioned reads and writes so that no file cursor is shared between callers.
+///
+/// The disk manager is `Send + Sync` and all of its methods take `&self`, so a single instance can
+/// be shared (e.g. behind an [std::sync::Arc]) by buffer pool workers issuing concurrent page I/O.
+/// Accesses to the same page are serialized by a sharded set of read-write latches, so a read
+/// never observes a partially written page, while accesses to different pages mostly proceed in
+/// parallel.
+// Not used until the buffer pool is built on top of it.
+#[allow(dead_code)]
 #[derive(Debug)]
 pub struct DiskManager {
+    /// The last page id handed out by `allocate_pages()`. It's only advanced once the file covers
+    /// the new pages, so every page id up to it can be read and written. `open()` derives it from
+    /// the file size, which maintains the same invariant.
     last_allocated_pid: std::sync::atomic::AtomicU64,
-    reader: BufReader<std::fs::File>,
-    writer: BufWriter<std::fs::File>,
+    /// Serializes allocations, which reserve, extend the file for, and publish a range of pages as
+    /// one step. Reads and writes don't take it.
+    allocation_latch: std::sync::Mutex<()>,
+    page_size: usize,
+    file: std::fs::File,
+    /// Page latches, indexed by page id modulo `PAGE_LATCH_SHARDS`.
+    latches: [RwLock<()>; PAGE_LATCH_SHARDS],
+    /// The directory holding a temporary database. Declared last so that it's dropped, and the
+    /// directory deleted, after the file is closed.
+    temp_dir: Option<TempDir>,
 }
 
+#[allow(dead_code)]
 impl DiskManager {
-    /// Creates a new disk manager for the given database file `filename`, e.g. `example.db`.
-    pub(crate) fn new(filename: &str) -> Result<Self> {
-        let path = Path::new(DATA_DIR).join(filename);
+    /// Creates a new database file `filename`, e.g. `example.db`, with pages of `page_size` bytes.
+    /// An existing file of the same name is truncated.
+    pub(crate) fn new(filename: &str, page_size: usize) -> Result<Self> {
+        Self::create(&Path::new(DATA_DIR).join(filename), page_size, None)
+    }
+
+    /// Creates a new database with pages of `page_size` bytes in a unique temporary directory,
+    /// outside of `DATA_DIR`. The directory and its contents are deleted when the disk manager is
+    /// dropped.
+    pub(crate) fn new_temporary(page_size: usize) -> Result<Self> {
+        let temp_dir = TempDir::new()?;
+        Self::create(
+            &temp_dir.path().join("temporary.db"),
+            page_size,
+            Some(temp_dir),
+        )
+    }
+
+    fn create(path: &Path, page_size: usize, temp_dir: Option<TempDir>) -> Result<Self> {
+        if !PAGE_SIZES_BYTES.contains(&page_size) {
+            return errinput!("Page size must be one of {PAGE_SIZES_BYTES:?}, got {page_size}.");
+        }
+
         let file = std::fs::OpenOptions::new()
             .write(true)
             .read(true)
             .create(true)
-            .open(&path)
-            .expect(format!("Unable to create or open file {}.", path.display()).as_str());
+            .truncate(true)
+            .open(path)?;
 
-        let reader = file;
-        let writer = reader
-            .try_clone()
-            .expect(format!("Unable to clone reader for file {}.", path.display()).as_str());
+        let disk_manager = Self::from_file(file, page_size, HEADER_PAGE_ID, temp_dir);
 
-        let mut disk_manager = Self {
-            last_allocated_pid: std::sync::atomic::AtomicU64::new(0),
-            reader: BufReader::new(reader),
-            writer: BufWriter::new(writer),
-        };
-
-        // Initialize the first page, potentially clearing out any garbage data.
-        disk_manager.write(&0, EMPTY_BUFFER)?;
+        // Initialize the header page, recording the format version and page size so that they can
+        // be validated when the database is opened again.
+        let mut header = Vec::with_capacity(page_size);
+        header.put_slice(MAGIC);
+        header.put_u32(FORMAT_VERSION);
+        header.put_u32(page_size as u32);
+        header.resize(page_size, 0);
+        disk_manager.write_page(&HEADER_PAGE_ID, &header)?;
 
         Ok(disk_manager)
     }
 
-    pub fn allocate_page(&mut self) -> Result<PageId> {
-        // `fetch_add` increments the current value and returns the old value.
-        let page_id = 1 + self
+    /// Opens the existing database file `filename`, validating its header. The page size is read
+    /// from the header, and the allocated pages are those that fit in the file.
+    pub(crate) fn open(filename: &str) -> Result<Self> {
+        Self::open_path(&Path::new(DATA_DIR).join(filename))
+    }
+
+    fn open_path(path: &Path) -> Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .read(true)
+            .open(path)?;
+
+        let file_size = file.metadata()?.len();
+        if file_size < HEADER_SIZE_BYTES as u64 {
+            return errdata!("File {} is too small to be a database.", path.display());
+        }
+
+        let mut header = [0; HEADER_SIZE_BYTES];
+        read_at(&file, &mut header, 0)?;
+        let mut header = &header[..];
+        if header[..MAGIC.len()] != MAGIC[..] {
+            return errdata!("File {} is not a database.", path.display());
+        }
+        header.advance(MAGIC.len());
+
+        let version = header.get_u32();
+        if version != FORMAT_VERSION {
+            return errdata!(
+                "Unsupported format version {version} in header of {}, expected {FORMAT_VERSION}.",
+                path.display()
+            );
+        }
+
+        let page_size = header.get_u32() as usize;
+        if !PAGE_SIZES_BYTES.contains(&page_size) {
+            return errdata!(
+                "Invalid page size {page_size} in header of {}.",
+                path.display()
+            );
+        }
+        if file_size % page_size as u64 != 0 {
+            return errdata!(
+                "Size {file_size} of file {} is not a multiple of its page size {page_size}.",
+                path.display()
+            );
+        }
+
+        let last_allocated_pid = file_size / page_size as u64 - 1;
+        Ok(Self::from_file(file, page_size, last_allocated_pid, None))
+    }
+
+    fn from_file(
+        file: std::fs::File,
+        page_size: usize,
+        last_allocated_pid: PageId,
+        temp_dir: Option<TempDir>,
+    ) -> Self {
+        Self {
+            last_allocated_pid: std::sync::atomic::AtomicU64::new(last_allocated_pid),
+            allocation_latch: std::sync::Mutex::new(()),
+            page_size,
+            file,
+            latches: std::array::from_fn(|_| RwLock::new(())),
+            temp_dir,
+        }
+    }
+
+    /// The size of every page in the database, in bytes.
+    pub(crate) fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    pub fn allocate_page(&self) -> Result<PageId> {
+        Ok(self.allocate_pages(1)?.start)
+    }
+
+    /// Allocates `n` contiguous pages, returning their ids. The file is extended to cover all of
+    /// them with a single write, and the ids are only published once that write succeeds, so a
+    /// failed allocation leaves the disk manager unchanged.
+    pub fn allocate_pages(&self, n: u64) -> Result<Range<PageId>> {
+        let _allocation = self.allocation_latch.lock()?;
+        let last_allocated_pid = self
             .last_allocated_pid
-            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            .load(std::sync::atomic::Ordering::SeqCst);
+        let start = last_allocated_pid
+            .checked_add(1)
+            .ok_or(Error::ArithmeticOverflow)?;
+        let end = start.checked_add(n).ok_or(Error::ArithmeticOverflow)?;
+        if n == 0 {
+            return Ok(start..end);
+        }
 
-        self.write(&page_id, EMPTY_BUFFER)?;
-        Ok(page_id)
+        // The file must be able to grow to cover the whole range.
+        self.calculate_offset(&end)?;
+
+        // Writing the last page extends the file past the whole range. The pages before it lie
+        // beyond the previous end of the file, so they read back as zeroes too. The range isn't
+        // published yet, so the write bypasses the allocation check in `write()`.
+        self.write_page(&(end - 1), &EMPTY_BUFFER[..self.page_size])?;
+        self.last_allocated_pid
+            .store(end - 1, std::sync::atomic::Ordering::SeqCst);
+        Ok(start..end)
     }
 
-    pub(crate) fn read(&mut self, page_id: &PageId) -> Result<Bytes> {
-        self.reader
-            .seek(SeekFrom::Start(Self::calculate_offset(page_id)?))?;
-        let mut bytes = BytesMut::zeroed(PAGE_SIZE_BYTES);
-        self.reader.read_exact(&mut bytes)?;
+    pub(crate) fn read(&self, page_id: &PageId) -> Result<Bytes> {
+        self.check_allocated(page_id)?;
+        let offset = self.calculate_offset(page_id)?;
+        let mut bytes = BytesMut::zeroed(self.page_size);
+        let _latch = self.latch(page_id).read()?;
+        read_at(&self.file, &mut bytes, offset)?;
         Ok(bytes.freeze())
     }
 
-    pub(crate) fn write(&mut self, page_id: &PageId, data: &[u8]) -> Result<()> {
-        if data.len() > PAGE_SIZE_BYTES {
+    pub(crate) fn write(&self, page_id: &PageId, data: &[u8]) -> Result<()> {
+        if *page_id == HEADER_PAGE_ID {
+            return errinput!("Page {HEADER_PAGE_ID} is reserved for the database header.");
+        }
+        self.check_allocated(page_id)?;
+        self.write_page(page_id, data)
+    }
+
+    fn write_page(&self, page_id: &PageId, data: &[u8]) -> Result<()> {
+        if data.len() > self.page_size {
             return errdata!("Page data must fit in a page.");
         }
 
-        let offset = Self::calculate_offset(page_id)?;
-        self.writer.seek(SeekFrom::Start(offset))?;
-        self.writer.write_all(data)?;
-        self.writer.flush()?;
+        let offset = self.calculate_offset(page_id)?;
+        let _latch = self.latch(page_id).write()?;
+        write_at(&self.file, data, offset)?;
+        Ok(())
+    }
+
+    /// Errors if `page_id` is beyond the allocated range, since the file holds either nothing or
+    /// stale bytes for it. Any page id that passes the check is covered by the file.
+    fn check_allocated(&self, page_id: &PageId) -> Result<()> {
+        if *page_id
+            > self
+                .last_allocated_pid
+                .load(std::sync::atomic::Ordering::SeqCst)
+        {
+            return Err(Error::PageNotAllocated(*page_id));
+        }
         Ok(())
     }
 
-    fn calculate_offset(page_id: &PageId) -> Result<u64> {
-        match (*page_id).checked_mul(PAGE_SIZE_BYTES as u64) {
+    fn latch(&self, page_id: &PageId) -> &RwLock<()> {
+        &self.latches[(*page_id % PAGE_LATCH_SHARDS as u64) as usize]
+    }
+
+    fn calculate_offset(&self, page_id: &PageId) -> Result<u64> {
+        match (*page_id).checked_mul(self.page_size as u64) {
             Some(value) => Ok(value),
             None => Err(Error::ArithmeticOverflow),
         }
     }
 }
 
+/// Reads exactly `buf.len()` bytes from `file`, starting at `offset`.
+#[cfg(unix)]
+fn read_at(file: &std::fs::File, buf: &mut [u8], offset: u64) -> std::io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+}
+
+/// Writes all of `buf` to `file`, starting at `offset`.
+#[cfg(unix)]
+fn write_at(file: &std::fs::File, buf: &[u8], offset: u64) -> std::io::Result<()> {
+    std::os::unix::fs::FileExt::write_all_at(file, buf, offset)
+}
+
+/// Reads exactly `buf.len()` bytes from `file`, starting at `offset`.
+#[cfg(windows)]
+fn read_at(file: &std::fs::File, mut buf: &mut [u8], mut offset: u64) -> std::io::Result<()> {
+    while !buf.is_empty() {
+        match std::os::windows::fs::FileExt::seek_read(file, buf, offset)? {
+            0 => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+            n => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes all of `buf` to `file`, starting at `offset`.
+#[cfg(windows)]
+fn write_at(file: &std::fs::File, mut buf: &[u8], mut offset: u64) -> std::io::Result<()> {
+    while !buf.is_empty() {
+        match std::os::windows::fs::FileExt::seek_write(file, buf, offset)? {
+            0 => return Err(std::io::ErrorKind::WriteZero.into()),
+            n => {
+                buf = &buf[n..];
+                offset += n as u64;
+            }
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::disk::disk_manager::{DiskManager, EMPTY_BUFFER};
-    use crate::PAGE_SIZE_BYTES;
+    use crate::disk::disk_manager::{
+        DiskManager, EMPTY_BUFFER, FORMAT_VERSION, HEADER_PAGE_ID, MAGIC,
+    };
+    use crate::disk::temp_storage::TempDir;
+    use crate::disk::{DATA_DIR, DEFAULT_PAGE_SIZE_BYTES, PAGE_SIZES_BYTES};
     use bytes::{Buf, BufMut};
+    use rustdb_error::Error;
+    use std::path::Path;
     use std::sync::atomic::Ordering::SeqCst;
+    use std::sync::Arc;
 
     #[test]
     fn test_new() {
         // We're able to open/create a file within the DATA_DIR directory.
-        let mut disk_manager = DiskManager::new("test.db").unwrap();
+        let disk_manager = DiskManager::new("test_new.db", DEFAULT_PAGE_SIZE_BYTES).unwrap();
 
-        // The page of a newly initialized disk manager should be of size `PAGE_SIZE_BYTES` filled
-        // with 0 bytes, and should have a PageId of 0.
+        // A newly initialized disk manager should only contain the header page, of size
+        // `DEFAULT_PAGE_SIZE_BYTES`, with a PageId of 0.
         let page_id = disk_manager.last_allocated_pid.load(SeqCst);
-        assert_eq!(page_id, 0);
-        let page = disk_manager.read(&page_id).unwrap();
-        assert_eq!(page.len(), PAGE_SIZE_BYTES);
-        assert_eq!(page.as_ref(), EMPTY_BUFFER);
+        assert_eq!(page_id, HEADER_PAGE_ID);
+        let mut page = disk_manager.read(&page_id).unwrap();
+        assert_eq!(page.len(), DEFAULT_PAGE_SIZE_BYTES);
+
+        // The header starts with the magic bytes, format version, and page size, and is otherwise
+        // zeroed.
+        assert_eq!(&page[..MAGIC.len()], MAGIC);
+        page.advance(MAGIC.len());
+        assert_eq!(page.get_u32(), FORMAT_VERSION);
+        assert_eq!(page.get_u32() as usize, DEFAULT_PAGE_SIZE_BYTES);
+        assert_eq!(page.as_ref(), &EMPTY_BUFFER[..page.len()]);
+
+        drop(disk_manager);
+        std::fs::remove_file(Path::new(DATA_DIR).join("test_new.db")).unwrap();
     }
 
     #[test]
     fn test_allocate_page() {
-        let mut disk_manager = DiskManager::new("test.db").unwrap();
+        let disk_manager = DiskManager::new_temporary(DEFAULT_PAGE_SIZE_BYTES).unwrap();
 
         // `allocate_page()` should increment the current PageId and return the new one.
         let page_id = disk_manager.allocate_page().unwrap();
         assert_eq!(page_id, 1);
         assert_eq!(disk_manager.last_allocated_pid.load(SeqCst), page_id);
 
-        // The allocated page corresponding to the new PageId should be of size `PAGE_SIZE_BYTES`,
-        // filled with 0 bytes.
+        // The allocated page corresponding to the new PageId should be of size
+        // `DEFAULT_PAGE_SIZE_BYTES`, filled with 0 bytes.
         let page = disk_manager.read(&page_id).unwrap();
-        assert_eq!(page.len(), PAGE_SIZE_BYTES);
-        assert_eq!(page.as_ref(), EMPTY_BUFFER);
+        assert_eq!(page.len(), DEFAULT_PAGE_SIZE_BYTES);
+        assert_eq!(page.as_ref(), &EMPTY_BUFFER[..DEFAULT_PAGE_SIZE_BYTES]);
+    }
+
+    #[test]
+    fn test_allocate_pages() {
+        let disk_manager = DiskManager::new_temporary(DEFAULT_PAGE_SIZE_BYTES).unwrap();
+        disk_manager.allocate_page().unwrap();
+
+        // `allocate_pages()` should reserve the next `n` PageIds, extending the file to cover
+        // them, with every page filled with 0 bytes.
+        let page_ids = disk_manager.allocate_pages(10).unwrap();
+        assert_eq!(page_ids, 2..12);
+        assert_eq!(disk_manager.last_allocated_pid.load(SeqCst), 11);
+        assert_eq!(
+            disk_manager.file.metadata().unwrap().len(),
+            12 * DEFAULT_PAGE_SIZE_BYTES as u64
+        );
+        for page_id in page_ids {
+            let page = disk_manager.read(&page_id).unwrap();
+            assert_eq!(page.as_ref(), &EMPTY_BUFFER[..DEFAULT_PAGE_SIZE_BYTES]);
+        }
+
+        // Allocating no pages returns an empty range and leaves the allocation unchanged.
+        assert!(disk_manager.allocate_pages(0).unwrap().is_empty());
+        assert_eq!(disk_manager.allocate_page().unwrap(), 12);
+    }
+
+    #[test]
+    fn test_allocate_pages_overflow() {
+        let disk_manager = DiskManager::new_temporary(DEFAULT_PAGE_SIZE_BYTES).unwrap();
+        disk_manager.allocate_page().unwrap();
+
+        // A range whose offset, or whose page ids, overflow a u64 can't be allocated.
+        for n in [1 << 60, u64::MAX] {
+            assert_eq!(
+                disk_manager.allocate_pages(n),
+                Err(Error::ArithmeticOverflow)
+            );
+        }
+
+        // The failed allocations leave the disk manager unchanged: nothing past the last
+        // allocated page is readable, and later allocations continue where it left off.
+        assert_eq!(disk_manager.last_allocated_pid.load(SeqCst), 1);
+        assert_eq!(disk_manager.read(&5), Err(Error::PageNotAllocated(5)));
+        assert_eq!(disk_manager.allocate_page().unwrap(), 2);
+        assert_eq!(disk_manager.allocate_pages(2).unwrap(), 3..5);
+    }
+
+    #[test]
+    fn test_unallocated_pages() {
+        let disk_manager = DiskManager::new_temporary(DEFAULT_PAGE_SIZE_BYTES).unwrap();
+        let page_id = disk_manager.allocate_page().unwrap();
+
+        // Pages past the last allocated one can be neither read nor written.
+        let next = page_id + 1;
+        assert_eq!(disk_manager.read(&next), Err(Error::PageNotAllocated(next)));
+        assert_eq!(
+            disk_manager.write(&next, &[1; 10]),
+            Err(Error::PageNotAllocated(next))
+        );
+        assert_eq!(
+            disk_manager.read(&u64::MAX),
+            Err(Error::PageNotAllocated(u64::MAX))
+        );
+
+        // Once allocated, they can.
+        assert_eq!(disk_manager.allocate_page().unwrap(), next);
+        assert!(disk_manager.write(&next, &[1; 10]).is_ok());
+        assert_eq!(&disk_manager.read(&next).unwrap()[..10], &[1; 10]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_unallocated_pages_after_failed_allocation() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test.db");
+        let disk_manager = DiskManager::create(&path, DEFAULT_PAGE_SIZE_BYTES, None).unwrap();
+        disk_manager.allocate_page().unwrap();
+
+        // Extending the file to the end of this range needs a write at offset 2^63, which fits in a
+        // u64 but is rejected by the OS.
+        let last = 1 << 51;
+        let result = disk_manager.allocate_pages(last - 1);
+        assert!(matches!(result, Err(Error::IO(_))));
+
+        // None of the pages in the failed range count as allocated, neither for this disk manager
+        // nor after reopening the file.
+        for page_id in [2, 3, last] {
+            assert_eq!(
+                disk_manager.read(&page_id),
+                Err(Error::PageNotAllocated(page_id))
+            );
+        }
+        drop(disk_manager);
+        let disk_manager = DiskManager::open_path(&path).unwrap();
+        assert_eq!(disk_manager.read(&2), Err(Error::PageNotAllocated(2)));
+        assert_eq!(disk_manager.allocate_page().unwrap(), 2);
     }
 
     #[test]
     fn test_page_access() {
-        let mut disk_manager = DiskManager::new("test.db").unwrap();
+        let disk_manager = DiskManager::new_temporary(DEFAULT_PAGE_SIZE_BYTES).unwrap();
         let mut buffer = Vec::new();
 
-        // We should be able to write floats to the first page and read them back.
+        // We should be able to write floats to the first allocated page and read them back.
+        disk_manager.allocate_page().unwrap();
         let float_vals: Vec<f64> = (0..100).map(|i| i as f64 * 1.1).collect();
         float_vals.iter().for_each(|f| buffer.put_f64(*f));
-        disk_manager.write(&0, &buffer).unwrap();
+        disk_manager.write(&1, &buffer).unwrap();
 
-        let mut first_page = disk_manager.read(&0).unwrap();
+        let mut first_page = disk_manager.read(&1).unwrap();
         float_vals
             .iter()
             .for_each(|f| assert_eq!(first_page.get_f64(), *f));
@@ -142,11 +467,155 @@ mod tests {
         disk_manager.allocate_page().unwrap();
         let int_vals: Vec<i32> = (0..100).map(|i| i).collect();
         int_vals.iter().for_each(|i| buffer.put_i32(*i));
-        disk_manager.write(&1, &buffer).unwrap();
+        disk_manager.write(&2, &buffer).unwrap();
 
-        let mut second_page = disk_manager.read(&1).unwrap();
+        let mut second_page = disk_manager.read(&2).unwrap();
         int_vals
             .iter()
             .for_each(|i| assert_eq!(second_page.get_i32(), *i));
+
+        // The header page can't be overwritten.
+        assert!(disk_manager.write(&HEADER_PAGE_ID, &buffer).is_err());
+    }
+
+    #[test]
+    fn test_page_sizes() {
+        // Every supported page size can be chosen at creation time, and determines the size of
+        // allocated pages.
+        for page_size in PAGE_SIZES_BYTES {
+            let disk_manager = DiskManager::new_temporary(page_size).unwrap();
+            assert_eq!(disk_manager.page_size(), page_size);
+
+            let page_id = disk_manager.allocate_page().unwrap();
+            assert_eq!(disk_manager.read(&page_id).unwrap().len(), page_size);
+            assert!(disk_manager.write(&page_id, &vec![1; page_size]).is_ok());
+            assert!(disk_manager
+                .write(&page_id, &vec![1; page_size + 1])
+                .is_err());
+        }
+
+        // Any other page size is rejected.
+        let result = DiskManager::new_temporary(1000);
+        assert!(matches!(result, Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_new_unwritable_path() {
+        // Failing to create the file is returned as an IO error rather than panicking.
+        let result = DiskManager::new("missing/test.db", DEFAULT_PAGE_SIZE_BYTES);
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+
+    #[test]
+    fn test_new_temporary() {
+        // A temporary database lives in its own directory outside of DATA_DIR, and is usable like
+        // any other.
+        let disk_manager = DiskManager::new_temporary(DEFAULT_PAGE_SIZE_BYTES).unwrap();
+        let temp_dir = disk_manager.temp_dir.as_ref().unwrap().path().to_path_buf();
+        assert!(!temp_dir.starts_with(DATA_DIR));
+        assert!(temp_dir.join("temporary.db").exists());
+
+        let page_id = disk_manager.allocate_page().unwrap();
+        disk_manager.write(&page_id, &[1; 10]).unwrap();
+        assert_eq!(&disk_manager.read(&page_id).unwrap()[..10], &[1; 10]);
+
+        // Temporary databases don't share a directory.
+        let other = DiskManager::new_temporary(DEFAULT_PAGE_SIZE_BYTES).unwrap();
+        assert_ne!(other.temp_dir.as_ref().unwrap().path(), temp_dir);
+
+        // Dropping the disk manager deletes the directory and the database in it.
+        drop(disk_manager);
+        assert!(!temp_dir.exists());
+    }
+
+    #[test]
+    fn test_concurrent_access() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<DiskManager>();
+
+        // Threads sharing a disk manager can allocate, write, and read pages concurrently, each
+        // seeing only its own writes.
+        let disk_manager = Arc::new(DiskManager::new_temporary(DEFAULT_PAGE_SIZE_BYTES).unwrap());
+        let handles: Vec<_> = (0..8u8)
+            .map(|thread| {
+                let disk_manager = Arc::clone(&disk_manager);
+                std::thread::spawn(move || {
+                    for _ in 0..50 {
+                        let page_id = disk_manager.allocate_page().unwrap();
+                        let data = vec![thread; DEFAULT_PAGE_SIZE_BYTES];
+                        disk_manager.write(&page_id, &data).unwrap();
+                        assert_eq!(disk_manager.read(&page_id).unwrap().as_ref(), data);
+                    }
+                })
+            })
+            .collect();
+        handles.into_iter().for_each(|h| h.join().unwrap());
+
+        // Every allocation got a distinct page id.
+        assert_eq!(disk_manager.last_allocated_pid.load(SeqCst), 8 * 50);
+    }
+
+    #[test]
+    fn test_open() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test.db");
+        let page_size = 16 * 1024;
+        let disk_manager = DiskManager::create(&path, page_size, None).unwrap();
+        disk_manager.allocate_page().unwrap();
+        let page_id = disk_manager.allocate_page().unwrap();
+        disk_manager.write(&page_id, &[7; 10]).unwrap();
+        drop(disk_manager);
+
+        // Reopening the database recovers its page size and allocated pages from the file.
+        let disk_manager = DiskManager::open_path(&path).unwrap();
+        assert_eq!(disk_manager.page_size(), page_size);
+        assert_eq!(disk_manager.last_allocated_pid.load(SeqCst), page_id);
+        assert_eq!(&disk_manager.read(&page_id).unwrap()[..10], &[7; 10]);
+        assert_eq!(disk_manager.allocate_page().unwrap(), page_id + 1);
+    }
+
+    #[test]
+    fn test_open_invalid() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test.db");
+
+        // A file without the magic bytes isn't a database.
+        std::fs::write(&path, vec![1; DEFAULT_PAGE_SIZE_BYTES]).unwrap();
+        let result = DiskManager::open_path(&path);
+        assert!(matches!(result, Err(Error::InvalidData(_))));
+
+        // Neither is one whose header records an unknown format version, e.g. from a newer release.
+        let mut header = Vec::new();
+        header.put_slice(MAGIC);
+        header.put_u32(FORMAT_VERSION + 1);
+        header.put_u32(DEFAULT_PAGE_SIZE_BYTES as u32);
+        header.resize(DEFAULT_PAGE_SIZE_BYTES, 0);
+        std::fs::write(&path, &header).unwrap();
+        let result = DiskManager::open_path(&path);
+        assert!(matches!(result, Err(Error::InvalidData(_))));
+
+        // Or an unsupported page size.
+        let mut header = Vec::new();
+        header.put_slice(MAGIC);
+        header.put_u32(FORMAT_VERSION);
+        header.put_u32(1000);
+        header.resize(DEFAULT_PAGE_SIZE_BYTES, 0);
+        std::fs::write(&path, &header).unwrap();
+        let result = DiskManager::open_path(&path);
+        assert!(matches!(result, Err(Error::InvalidData(_))));
+
+        // Or one that isn't a whole number of pages long.
+        let mut header = Vec::new();
+        header.put_slice(MAGIC);
+        header.put_u32(FORMAT_VERSION);
+        header.put_u32(DEFAULT_PAGE_SIZE_BYTES as u32);
+        header.resize(DEFAULT_PAGE_SIZE_BYTES + 1, 0);
+        std::fs::write(&path, &header).unwrap();
+        let result = DiskManager::open_path(&path);
+        assert!(matches!(result, Err(Error::InvalidData(_))));
+
+        // Opening a file that doesn't exist is an IO error.
+        let result = DiskManager::open_path(&temp_dir.path().join("missing.db"));
+        assert!(matches!(result, Err(Error::IO(_))));
     }
 }
@@ -1,5 +1,12 @@
 //! The disk manager for the storage engine. Responsible for reading and writing to
-//! database pages on disk.
+//! database pages on disk, and for the short-lived files used to spill intermediate state.
 mod disk_manager;
+mod temp_storage;
 
 pub(crate) const DATA_DIR: &str = "src/disk/data/";
+
+/// The page size of a new database, unless another is chosen when it is created.
+#[cfg_attr(not(test), allow(dead_code))]
+pub(crate) const DEFAULT_PAGE_SIZE_BYTES: usize = 4 * 1024;
+/// The page sizes a database can be created with. The chosen size is recorded in the header.
+pub(crate) const PAGE_SIZES_BYTES: [usize; 4] = [4 * 1024, 8 * 1024, 16 * 1024, 64 * 1024];
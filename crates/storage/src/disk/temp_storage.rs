@@ -0,0 +1,180 @@
+use rustdb_error::Result;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The name prefix of the per-operation directories created by [`TempStorage`]. Only entries with
+/// this prefix are removed when cleaning up after a crash.
+const OPERATION_DIR_PREFIX: &str = "rustdb-temp-";
+
+/// Manages short-lived files, e.g. for sort and hash spills or index builds, in a configurable
+/// directory. Each operation gets its own [`TempDir`], so that its files are tracked together and
+/// deleted together once the operation drops it.
+///
+/// Files left behind by a crash are deleted the next time a `TempStorage` is created for the same
+/// directory, so the directory must not be shared with another running database.
+// Not used until the spilling operators and index builds are built on top of it.
+#[allow(dead_code)]
+#[derive(Debug)]
+pub(crate) struct TempStorage {
+    dir: PathBuf,
+    next_operation_id: AtomicU64,
+}
+
+#[allow(dead_code)]
+impl TempStorage {
+    /// Uses `dir` for temporary files, creating it if needed and removing anything left in it by
+    /// an earlier instance.
+    pub(crate) fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            if !entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(OPERATION_DIR_PREFIX)
+            {
+                continue;
+            }
+            if entry.file_type()?.is_dir() {
+                std::fs::remove_dir_all(entry.path())?;
+            } else {
+                std::fs::remove_file(entry.path())?;
+            }
+        }
+
+        Ok(Self {
+            dir,
+            next_operation_id: AtomicU64::new(0),
+        })
+    }
+
+    /// Creates a directory for the temporary files of a single operation.
+    pub(crate) fn create_dir(&self) -> Result<TempDir> {
+        TempDir::create_unique(&self.dir, OPERATION_DIR_PREFIX, &self.next_operation_id)
+    }
+}
+
+/// A uniquely named temporary directory, deleted along with its contents on drop.
+#[derive(Debug)]
+pub(crate) struct TempDir {
+    path: PathBuf,
+    next_file_id: AtomicU64,
+}
+
+impl TempDir {
+    /// Creates a directory under the system's temporary directory. Unlike those created by
+    /// [`TempStorage`], it isn't cleaned up after a crash.
+    pub(crate) fn new() -> Result<Self> {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        let prefix = format!("rustdb-{}-", std::process::id());
+        Self::create_unique(&std::env::temp_dir(), &prefix, &NEXT_ID)
+    }
+
+    fn create_unique(parent: &Path, prefix: &str, next_id: &AtomicU64) -> Result<Self> {
+        loop {
+            let id = next_id.fetch_add(1, Ordering::SeqCst);
+            let path = parent.join(format!("{prefix}{id}"));
+            match std::fs::create_dir(&path) {
+                Ok(()) => {
+                    return Ok(Self {
+                        path,
+                        next_file_id: AtomicU64::new(0),
+                    })
+                }
+                // Left behind by an earlier process, e.g. after a crash.
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Creates a new, empty file in the directory, open for reading and writing. On Windows, the
+    /// file must be closed before the directory is dropped for it to be deleted.
+    // Only temp storage operations create files, and those aren't used yet either.
+    #[allow(dead_code)]
+    pub(crate) fn create_file(&self) -> Result<std::fs::File> {
+        let id = self.next_file_id.fetch_add(1, Ordering::SeqCst);
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(self.path.join(format!("{id}.tmp")))?;
+        Ok(file)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        // Cleanup is best-effort; there's nothing useful to do with an error while dropping.
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::disk::temp_storage::{TempDir, TempStorage};
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    #[test]
+    fn test_create_dir() {
+        let root = TempDir::new().unwrap();
+        let temp_storage = TempStorage::new(root.path()).unwrap();
+
+        // Each operation gets its own directory within the configured one.
+        let first = temp_storage.create_dir().unwrap();
+        let second = temp_storage.create_dir().unwrap();
+        assert_ne!(first.path(), second.path());
+        for dir in [&first, &second] {
+            assert_eq!(dir.path().parent().unwrap(), root.path());
+            assert!(dir.path().is_dir());
+        }
+
+        // Files are created in the operation's directory, and can be written and read back.
+        let mut file = first.create_file().unwrap();
+        file.write_all(b"spilled").unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "spilled");
+        drop(file);
+
+        first.create_file().unwrap();
+        assert_eq!(std::fs::read_dir(first.path()).unwrap().count(), 2);
+
+        // Dropping an operation's directory deletes it and its files, leaving others alone.
+        let path = first.path().to_path_buf();
+        drop(first);
+        assert!(!path.exists());
+        assert!(second.path().is_dir());
+    }
+
+    #[test]
+    fn test_cleanup_after_crash() {
+        let root = TempDir::new().unwrap();
+        let temp_storage = TempStorage::new(root.path()).unwrap();
+
+        // Simulate a crash by leaking an operation's directory, so it's never cleaned up.
+        let dir = temp_storage.create_dir().unwrap();
+        dir.create_file().unwrap();
+        let leaked = dir.path().to_path_buf();
+        std::mem::forget(dir);
+        drop(temp_storage);
+        assert!(leaked.exists());
+
+        // Entries that weren't created by the temp storage are never touched.
+        let unrelated = root.path().join("unrelated.txt");
+        std::fs::write(&unrelated, b"keep").unwrap();
+
+        // A new temp storage for the same directory removes the leftovers on startup.
+        let temp_storage = TempStorage::new(root.path()).unwrap();
+        assert!(!leaked.exists());
+        assert!(unrelated.exists());
+        assert!(temp_storage.create_dir().unwrap().path().is_dir());
+    }
+}
@@ -9,5 +9,3 @@
 
 mod disk;
 mod lock;
-
-const PAGE_SIZE_BYTES: usize = 4096;
@@ -2,6 +2,8 @@ use std::hash::Hash;
 use std::sync::Arc;
 
 /// A map of identifiers to async read-write locks.
+// Not used until transactions are built on top of it.
+#[allow(dead_code)]
 pub(crate) struct LockManager<T, I>
 where
     T: Eq + PartialEq + Hash,